@@ -0,0 +1,117 @@
+use clap::{App, Arg};
+use rswinthings::file::pipe::{PipeBackpressure, RecordSink};
+use rswinthings::mft::{EntryListener, OutputFormat, FLAT_ENTRY_CSV_COLUMNS};
+use rswinthings::utils::debug::set_debug_level;
+use std::process::exit;
+
+static VERSION: &'static str = "0.1.0";
+
+fn make_app<'a, 'b>() -> App<'a, 'b> {
+    let path_arg = Arg::with_name("path")
+        .short("p")
+        .long("path")
+        .value_name("PATH")
+        .help("The file path to monitor MFT changes for.")
+        .required(true)
+        .takes_value(true);
+
+    let namedpipe_arg = Arg::with_name("named_pipe")
+        .long("named_pipe")
+        .value_name("NAMEDPIPE")
+        .takes_value(true)
+        .help("The named pipe to write out to.");
+
+    let output_format_arg = Arg::with_name("output_format")
+        .long("output-format")
+        .value_name("FORMAT")
+        .takes_value(true)
+        .possible_values(&["jsonl", "csv"])
+        .help("The format to write records out in. (default: jsonl)");
+
+    let backpressure_arg = Arg::with_name("pipe_backpressure")
+        .long("pipe-backpressure")
+        .value_name("POLICY")
+        .takes_value(true)
+        .possible_values(&["buffer", "drop"])
+        .help("What to do with records while no named pipe consumer is attached. (default: buffer)");
+
+    let verbose = Arg::with_name("debug")
+        .short("-d")
+        .long("debug")
+        .value_name("DEBUG")
+        .takes_value(true)
+        .possible_values(&["Off", "Error", "Warn", "Info", "Debug", "Trace"])
+        .help("Debug level to use.");
+
+    App::new("listen_mft")
+        .version(VERSION)
+        .author("Matthew Seyer <https://github.com/forensicmatt/RustyUsn>")
+        .about("Single file MFT entry listener written in Rust. Output is JSONL or CSV.")
+        .arg(path_arg)
+        .arg(namedpipe_arg)
+        .arg(output_format_arg)
+        .arg(backpressure_arg)
+        .arg(verbose)
+}
+
+fn main() {
+    let app = make_app();
+    let options = app.get_matches();
+
+    match options.value_of("debug") {
+        Some(d) => set_debug_level(d).expect("Error setting debug level"),
+        None => {}
+    }
+
+    let path_to_monitor = match options.value_of("path") {
+        Some(p) => p,
+        None => {
+            eprintln!("listen_mft requires a file path to monitor.");
+            exit(-1);
+        }
+    };
+
+    let output_format = match options.value_of("output_format") {
+        Some("csv") => OutputFormat::Csv,
+        Some("jsonl") | None => OutputFormat::Jsonl,
+        Some(other) => {
+            eprintln!("Unknown output format: {}", other);
+            exit(-1);
+        }
+    };
+
+    let backpressure = match options.value_of("pipe_backpressure") {
+        Some("drop") => PipeBackpressure::Drop,
+        Some("buffer") | None => PipeBackpressure::Buffer,
+        Some(other) => {
+            eprintln!("Unknown pipe backpressure policy: {}", other);
+            exit(-1);
+        }
+    };
+
+    let sink = RecordSink::new(options.value_of("named_pipe"), backpressure)
+        .expect("Error creating pipe server");
+
+    let listener = EntryListener::new(path_to_monitor)
+        .expect("Error creating entry listener")
+        .output_format(output_format);
+
+    let receiver = listener.listen_to_file().expect("Error listening to file");
+
+    let mut csv_header_written = false;
+
+    loop {
+        let line = match receiver.recv() {
+            Ok(l) => l,
+            Err(_) => break,
+        };
+
+        if output_format == OutputFormat::Csv && !csv_header_written {
+            sink.write_line(&FLAT_ENTRY_CSV_COLUMNS.join(","))
+                .expect("Unable to write header");
+            csv_header_written = true;
+        }
+
+        sink.write_line(&line).expect("Unable to write value");
+    }
+}
@@ -1,13 +1,55 @@
 use clap::{App, Arg};
-use rswinthings::file::pipe::create_pipe;
+use rswinthings::file::pipe::{PipeBackpressure, RecordSink};
 use rswinthings::handler::WindowsHandler;
+use rswinthings::mft::{csv_line, OutputFormat, PathResolver};
 use rswinthings::usn::listener::UsnListenerConfig;
 use rswinthings::utils::debug::set_debug_level;
-use std::io::Write;
+use rswinthings::volume::liventfs::WindowsLiveNtfs;
+use serde_json::{json, Value};
 use std::process::exit;
 
 static VERSION: &'static str = "0.2.0";
 
+/// The stable column order used when flattening a USN record to CSV.
+/// Nested fields are addressed with a dotted path (e.g. `file_reference.entry`).
+static USN_CSV_COLUMNS: &'static [&'static str] = &[
+    "usn",
+    "timestamp",
+    "reason",
+    "file_attributes",
+    "file_name",
+    "file_reference.entry",
+    "file_reference.sequence",
+    "parent_file_reference.entry",
+    "parent_file_reference.sequence",
+    "full_path",
+];
+
+/// Look up a (possibly dotted) field path within a JSON value and render
+/// it as a CSV cell, leaving the cell empty when the field is absent.
+fn usn_field_as_cell(value: &Value, path: &str) -> String {
+    let mut current = value;
+    for part in path.split('.') {
+        current = match current.get(part) {
+            Some(v) => v,
+            None => return String::new(),
+        };
+    }
+
+    match current {
+        Value::String(s) => s.to_owned(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn usn_value_to_csv_row(value: &Value) -> Vec<String> {
+    USN_CSV_COLUMNS
+        .iter()
+        .map(|column| usn_field_as_cell(value, column))
+        .collect()
+}
+
 fn make_app<'a, 'b>() -> App<'a, 'b> {
     let source_arg = Arg::with_name("source")
         .short("s")
@@ -35,6 +77,20 @@ fn make_app<'a, 'b>() -> App<'a, 'b> {
         .takes_value(true)
         .help("The named pipe to write out to.");
 
+    let output_format_arg = Arg::with_name("output_format")
+        .long("output-format")
+        .value_name("FORMAT")
+        .takes_value(true)
+        .possible_values(&["jsonl", "csv"])
+        .help("The format to write records out in. (default: jsonl)");
+
+    let backpressure_arg = Arg::with_name("pipe_backpressure")
+        .long("pipe-backpressure")
+        .value_name("POLICY")
+        .takes_value(true)
+        .possible_values(&["buffer", "drop"])
+        .help("What to do with records while no named pipe consumer is attached. (default: buffer)");
+
     let verbose = Arg::with_name("debug")
         .short("-d")
         .long("debug")
@@ -51,6 +107,8 @@ fn make_app<'a, 'b>() -> App<'a, 'b> {
         .arg(historical_arg)
         .arg(mask_arg)
         .arg(namedpipe_arg)
+        .arg(output_format_arg)
+        .arg(backpressure_arg)
         .arg(verbose)
 }
 
@@ -96,34 +154,67 @@ fn main() {
         None => {}
     }
 
-    let mut opt_named_pipe = match options.value_of("named_pipe") {
-        Some(p) => Some(create_pipe(p).expect("Error creating pipe")),
-        None => None,
+    let output_format = match options.value_of("output_format") {
+        Some("csv") => OutputFormat::Csv,
+        Some("jsonl") | None => OutputFormat::Jsonl,
+        Some(other) => {
+            eprintln!("Unknown output format: {}", other);
+            exit(-1);
+        }
     };
 
+    let backpressure = match options.value_of("pipe_backpressure") {
+        Some("drop") => PipeBackpressure::Drop,
+        Some("buffer") | None => PipeBackpressure::Buffer,
+        Some(other) => {
+            eprintln!("Unknown pipe backpressure policy: {}", other);
+            exit(-1);
+        }
+    };
+
+    let sink = RecordSink::new(options.value_of("named_pipe"), backpressure)
+        .expect("Error creating pipe server");
+
     let reciever = handler
         .listen_usn(source_volume, Some(config))
         .expect("Error creating listener");
 
+    let mut live_volume =
+        WindowsLiveNtfs::from_volume_path(source_volume).expect("Error opening source volume");
+    let mut resolver = PathResolver::new(&mut live_volume);
+
+    let mut csv_header_written = false;
+
     loop {
-        for value in reciever.recv() {
-            let value_str = match serde_json::to_string(&value) {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("Error creating string from value: {:?}", e);
-                    continue;
+        for mut value in reciever.recv() {
+            if let Some(entry_id) = value["file_reference"]["entry"].as_u64() {
+                let expected_sequence = value["file_reference"]["sequence"]
+                    .as_u64()
+                    .map(|s| s as u16);
+                let full_path = resolver.resolve(entry_id, expected_sequence);
+                if let Value::Object(ref mut map) = value {
+                    map.insert("full_path".to_string(), json!(full_path));
                 }
-            };
+            }
 
-            match opt_named_pipe {
-                Some(ref mut fh) => {
-                    fh.write(&format!("{}", value_str).into_bytes())
+            match output_format {
+                OutputFormat::Jsonl => match serde_json::to_string(&value) {
+                    Ok(s) => sink.write_line(&s).expect("Unable to write value"),
+                    Err(e) => {
+                        eprintln!("Error creating string from value: {:?}", e);
+                        continue;
+                    }
+                },
+                OutputFormat::Csv => {
+                    if !csv_header_written {
+                        sink.write_line(&USN_CSV_COLUMNS.join(","))
+                            .expect("Unable to write header");
+                        csv_header_written = true;
+                    }
+                    sink.write_line(&csv_line(&usn_value_to_csv_row(&value)))
                         .expect("Unable to write value");
                 }
-                None => {
-                    println!("{}", value_str);
-                }
-            }
+            };
         }
     }
 }
@@ -0,0 +1,209 @@
+use clap::{App, Arg};
+use rswinthings::errors::WinThingError;
+use rswinthings::file::pipe::{PipeBackpressure, RecordSink};
+use rswinthings::mft::{csv_line, custom_entry_value, FlatEntry, OutputFormat, FLAT_ENTRY_CSV_COLUMNS};
+use rswinthings::utils::debug::set_debug_level;
+use mft::entry::EntryFlags;
+use mft::MftEntry;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::process::exit;
+
+static VERSION: &'static str = "0.1.0";
+
+/// MFT records are fixed 1024-byte slots on disk.
+const MFT_RECORD_SIZE: u64 = 1024;
+
+fn make_app<'a, 'b>() -> App<'a, 'b> {
+    let source_arg = Arg::with_name("source")
+        .short("s")
+        .long("source")
+        .value_name("PATH")
+        .help("Path to an extracted $MFT (or raw volume image) to parse.")
+        .required(true)
+        .takes_value(true);
+
+    let start_arg = Arg::with_name("start")
+        .long("start")
+        .value_name("ENTRY")
+        .takes_value(true)
+        .help("The entry index to start parsing from. (default: 0)");
+
+    let count_arg = Arg::with_name("count")
+        .long("count")
+        .value_name("COUNT")
+        .takes_value(true)
+        .help("The number of entries to parse. (default: all remaining entries)");
+
+    let skip_unallocated_arg = Arg::with_name("skip_unallocated")
+        .long("skip-unallocated")
+        .help("Skip entries that are unallocated (deleted). Zeroed-out slots are always skipped.");
+
+    let namedpipe_arg = Arg::with_name("named_pipe")
+        .long("named_pipe")
+        .value_name("NAMEDPIPE")
+        .takes_value(true)
+        .help("The named pipe to write out to.");
+
+    let output_format_arg = Arg::with_name("output_format")
+        .long("output-format")
+        .value_name("FORMAT")
+        .takes_value(true)
+        .possible_values(&["jsonl", "csv"])
+        .help("The format to write records out in. (default: jsonl)");
+
+    let backpressure_arg = Arg::with_name("pipe_backpressure")
+        .long("pipe-backpressure")
+        .value_name("POLICY")
+        .takes_value(true)
+        .possible_values(&["buffer", "drop"])
+        .help("What to do with records while no named pipe consumer is attached. (default: buffer)");
+
+    let verbose = Arg::with_name("debug")
+        .short("-d")
+        .long("debug")
+        .value_name("DEBUG")
+        .takes_value(true)
+        .possible_values(&["Off", "Error", "Warn", "Info", "Debug", "Trace"])
+        .help("Debug level to use.");
+
+    App::new("parse_mft")
+        .version(VERSION)
+        .author("Matthew Seyer <https://github.com/forensicmatt/RustyUsn>")
+        .about("Offline $MFT image parser written in Rust. Output is JSONL or CSV.")
+        .arg(source_arg)
+        .arg(start_arg)
+        .arg(count_arg)
+        .arg(skip_unallocated_arg)
+        .arg(namedpipe_arg)
+        .arg(output_format_arg)
+        .arg(backpressure_arg)
+        .arg(verbose)
+}
+
+/// Read one 1024-byte record from `source` at `entry_id`, returning `None`
+/// once the image is exhausted.
+fn read_record(source: &mut File, entry_id: u64) -> Result<Option<Vec<u8>>, WinThingError> {
+    source.seek(SeekFrom::Start(entry_id * MFT_RECORD_SIZE))?;
+
+    let mut buffer = vec![0u8; MFT_RECORD_SIZE as usize];
+    match source.read_exact(&mut buffer) {
+        Ok(_) => Ok(Some(buffer)),
+        Err(ref e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn main() {
+    let app = make_app();
+    let options = app.get_matches();
+
+    match options.value_of("debug") {
+        Some(d) => set_debug_level(d).expect("Error setting debug level"),
+        None => {}
+    }
+
+    let source_path = match options.value_of("source") {
+        Some(s) => s,
+        None => {
+            eprintln!("parse_mft requires a source $MFT path.");
+            exit(-1);
+        }
+    };
+
+    let start: u64 = match options.value_of("start") {
+        Some(s) => s.parse().expect("Error parsing start entry"),
+        None => 0,
+    };
+
+    let count: Option<u64> = match options.value_of("count") {
+        Some(c) => Some(c.parse().expect("Error parsing count")),
+        None => None,
+    };
+
+    let skip_unallocated = options.is_present("skip_unallocated");
+
+    let output_format = match options.value_of("output_format") {
+        Some("csv") => OutputFormat::Csv,
+        Some("jsonl") | None => OutputFormat::Jsonl,
+        Some(other) => {
+            eprintln!("Unknown output format: {}", other);
+            exit(-1);
+        }
+    };
+
+    let backpressure = match options.value_of("pipe_backpressure") {
+        Some("drop") => PipeBackpressure::Drop,
+        Some("buffer") | None => PipeBackpressure::Buffer,
+        Some(other) => {
+            eprintln!("Unknown pipe backpressure policy: {}", other);
+            exit(-1);
+        }
+    };
+
+    let sink = RecordSink::new(options.value_of("named_pipe"), backpressure)
+        .expect("Error creating pipe server");
+
+    let mut source = File::open(source_path).expect("Error opening source $MFT image");
+
+    let mut csv_header_written = false;
+    let mut entry_id = start;
+    let mut processed = 0u64;
+
+    loop {
+        if let Some(count) = count {
+            if processed >= count {
+                break;
+            }
+        }
+
+        let buffer = match read_record(&mut source, entry_id).expect("Error reading record") {
+            Some(b) => b,
+            None => break,
+        };
+
+        entry_id += 1;
+        processed += 1;
+
+        if buffer.iter().all(|b| *b == 0) {
+            // Zeroed, never-allocated slot.
+            continue;
+        }
+
+        // Image bytes are exactly as they sit on disk, so (unlike the live
+        // volume path, whose buffers already come back fixed up) the update
+        // sequence array fixup has to be applied here.
+        let mft_entry = match MftEntry::from_buffer(buffer, entry_id - 1) {
+            Ok(e) => e,
+            Err(e) => {
+                eprintln!("Error parsing entry {}: {:?}", entry_id - 1, e);
+                continue;
+            }
+        };
+
+        if skip_unallocated && !mft_entry.header.flags.contains(EntryFlags::ALLOCATED) {
+            continue;
+        }
+
+        match output_format {
+            OutputFormat::Jsonl => {
+                let value: Value =
+                    custom_entry_value(&mft_entry, None).expect("Error building entry value");
+                let line = serde_json::to_string(&value).expect("Error serializing entry value");
+                sink.write_line(&line).expect("Unable to write value");
+            }
+            OutputFormat::Csv => {
+                let flat_entry = FlatEntry::from_entry(&mft_entry).expect("Error flattening entry");
+                if !csv_header_written {
+                    sink.write_line(&FLAT_ENTRY_CSV_COLUMNS.join(","))
+                        .expect("Unable to write header");
+                    csv_header_written = true;
+                }
+
+                sink.write_line(&csv_line(&flat_entry.to_csv_row()))
+                    .expect("Unable to write value");
+            }
+        };
+    }
+}
@@ -1,47 +1,175 @@
+use crate::errors::WinThingError;
+use crossbeam::channel::{self, Receiver, Sender};
 use std::fs::File;
-use std::os::windows::io::FromRawHandle;
+use std::io::Write;
+use std::os::windows::io::{AsRawHandle, FromRawHandle};
 use std::ptr::null_mut;
-use winapi::um::namedpipeapi::CreateNamedPipeW;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use winapi::shared::winerror::ERROR_PIPE_CONNECTED;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::namedpipeapi::{ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe};
 use winapi::um::winbase::{
-    PIPE_ACCESS_DUPLEX,
-    PIPE_TYPE_MESSAGE,
-    PIPE_READMODE_MESSAGE,
-    PIPE_WAIT
+    PIPE_ACCESS_DUPLEX, PIPE_READMODE_MESSAGE, PIPE_TYPE_MESSAGE, PIPE_UNLIMITED_INSTANCES,
+    PIPE_WAIT,
 };
-use winapi::um::handleapi::INVALID_HANDLE_VALUE;
-use crate::file::FileHandle;
-use crate::errors::WinThingError;
+use winapi::um::winnt::HANDLE;
 
-use winapi::um::fileapi::CreateFileW;
-use winapi::um::winnt::GENERIC_WRITE;
-use winapi::um::fileapi::OPEN_EXISTING;
+const PIPE_BUFFER_SIZE: u32 = 65536;
 
+/// Backoff between retries after a failed `ConnectNamedPipe`, so a
+/// persistent failure degrades into a slow poll instead of a CPU-spinning
+/// busy loop.
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(500);
 
-pub fn create_pipe(pipe_name: &str) -> Result<File, WinThingError> {
+/// What to do with a record produced while no consumer is attached to the pipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeBackpressure {
+    /// Keep every record (in an unbounded queue) until a consumer connects.
+    Buffer,
+    /// Discard records produced while no consumer is attached.
+    Drop,
+}
+
+fn create_named_pipe_handle(pipe_name: &str) -> Result<HANDLE, WinThingError> {
     let mut path_u16: Vec<u16> = pipe_name.to_string().encode_utf16().collect();
     path_u16.resize(path_u16.len() + 1, 0);
 
     let handle = unsafe {
-        CreateFileW(
+        CreateNamedPipeW(
             path_u16.as_ptr(),
-            GENERIC_WRITE,
+            PIPE_ACCESS_DUPLEX,
+            PIPE_TYPE_MESSAGE | PIPE_READMODE_MESSAGE | PIPE_WAIT,
+            PIPE_UNLIMITED_INSTANCES,
+            PIPE_BUFFER_SIZE,
+            PIPE_BUFFER_SIZE,
             0,
             null_mut(),
-            OPEN_EXISTING,
-            0,
-            null_mut()
         )
     };
 
     if handle == INVALID_HANDLE_VALUE {
-        return Err(
-            WinThingError::from_windows_last_error()
-        );
+        return Err(WinThingError::from_windows_last_error());
     }
 
-    let file = unsafe {
-        File::from_raw_handle(handle)
-    };
+    Ok(handle)
+}
+
+/// Block until a consumer connects to `handle`, treating the
+/// "a client already connected before we called ConnectNamedPipe" race
+/// as success rather than an error.
+fn wait_for_client(handle: HANDLE) -> Result<(), WinThingError> {
+    let connected = unsafe { ConnectNamedPipe(handle, null_mut()) };
+    if connected != 0 {
+        return Ok(());
+    }
+
+    if unsafe { GetLastError() } == ERROR_PIPE_CONNECTED {
+        return Ok(());
+    }
+
+    Err(WinThingError::from_windows_last_error())
+}
+
+fn run_pipe_server(mut pipe_file: File, receiver: Receiver<Vec<u8>>, connected: Arc<AtomicBool>) {
+    // `File` is `Send`, but the raw `HANDLE` it wraps is not, so the handle
+    // is re-derived from `pipe_file` on this side of the `thread::spawn`
+    // boundary rather than captured separately.
+    let handle = pipe_file.as_raw_handle() as HANDLE;
+    loop {
+        if let Err(e) = wait_for_client(handle) {
+            eprintln!("Error accepting named pipe client: {:?}", e);
+            thread::sleep(CONNECT_RETRY_DELAY);
+            continue;
+        }
+        connected.store(true, Ordering::SeqCst);
+
+        loop {
+            let message = match receiver.recv() {
+                Ok(m) => m,
+                // The PipeServer (and its sender) was dropped; shut down.
+                Err(_) => return,
+            };
 
-    Ok(file)
-}
\ No newline at end of file
+            // Message-mode pipes treat every WriteFile as one discrete
+            // message, so a plain write_all is enough to frame a record.
+            if pipe_file.write_all(&message).is_err() {
+                connected.store(false, Ordering::SeqCst);
+                unsafe { DisconnectNamedPipe(handle) };
+                break;
+            }
+        }
+    }
+}
+
+/// A named-pipe server that frames each record as one discrete pipe
+/// message and transparently accepts the next consumer - instead of
+/// panicking - when the current one disconnects mid-stream.
+pub struct PipeServer {
+    sender: Sender<Vec<u8>>,
+    connected: Arc<AtomicBool>,
+    backpressure: PipeBackpressure,
+}
+impl PipeServer {
+    /// Create the named pipe and start accepting consumers in the background.
+    pub fn create(pipe_name: &str, backpressure: PipeBackpressure) -> Result<Self, WinThingError> {
+        let handle = create_named_pipe_handle(pipe_name)?;
+        let pipe_file = unsafe { File::from_raw_handle(handle) };
+
+        let (sender, receiver): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = channel::unbounded();
+        let connected = Arc::new(AtomicBool::new(false));
+
+        let thread_connected = connected.clone();
+        thread::spawn(move || run_pipe_server(pipe_file, receiver, thread_connected));
+
+        Ok(Self {
+            sender,
+            connected,
+            backpressure,
+        })
+    }
+
+    /// Queue `message` to be written to the pipe as one discrete message.
+    /// Never blocks on a consumer being attached; see `PipeBackpressure`.
+    pub fn send(&self, message: Vec<u8>) -> Result<(), WinThingError> {
+        if self.backpressure == PipeBackpressure::Drop && !self.connected.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        self.sender.send(message).map_err(|_| {
+            WinThingError::from(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "pipe server thread has terminated",
+            ))
+        })
+    }
+}
+
+/// A sink that forwards one record at a time either to a `PipeServer` or,
+/// when no named pipe was requested, to stdout - the choice `listen_usn`
+/// already offered, now shared by `listen_usn`, `listen_mft` and `parse_mft`.
+pub enum RecordSink {
+    Pipe(PipeServer),
+    Stdout,
+}
+impl RecordSink {
+    pub fn new(pipe_name: Option<&str>, backpressure: PipeBackpressure) -> Result<Self, WinThingError> {
+        match pipe_name {
+            Some(name) => Ok(RecordSink::Pipe(PipeServer::create(name, backpressure)?)),
+            None => Ok(RecordSink::Stdout),
+        }
+    }
+
+    pub fn write_line(&self, line: &str) -> Result<(), WinThingError> {
+        match self {
+            RecordSink::Pipe(server) => server.send(line.as_bytes().to_vec()),
+            RecordSink::Stdout => {
+                println!("{}", line);
+                Ok(())
+            }
+        }
+    }
+}
@@ -4,14 +4,347 @@ use crate::usn::listener::UsnListenerConfig;
 use crate::utils::json::get_difference_value;
 use crate::volume::liventfs::WindowsLiveNtfs;
 use byteorder::{LittleEndian, ReadBytesExt};
+use chrono::NaiveDateTime;
 use crossbeam::channel::{self, Receiver, Sender};
-use mft::attribute::{MftAttribute, MftAttributeType};
+use mft::attribute::header::ResidentialHeader;
+use mft::attribute::x10::StandardInfoAttr;
+use mft::attribute::x30::{FileNameAttr, FileNamespace};
+use mft::attribute::{FileReference, MftAttribute, MftAttributeContent, MftAttributeType};
+use mft::entry::EntryFlags;
 use mft::MftEntry;
 use serde_json::to_value;
 use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use std::io::Read;
 use std::thread;
 
+/// The entry index of the NTFS volume root (`.`), where path reconstruction stops.
+const ROOT_ENTRY: u64 = 5;
+
+/// Synthetic parent name used for entries whose parent `$FILE_NAME` reference
+/// cannot be read (already deleted, corrupt, or outside the image) or that
+/// form a reference cycle.
+const ORPHAN_NAME: &str = "$Orphan";
+
+/// A cached path lookup, tagged with the MFT sequence number the entry had
+/// when the path was resolved, so a later record reuse (the entry number
+/// recycled for an unrelated file) can be detected instead of silently
+/// served from the cache.
+struct CachedPath {
+    sequence: u16,
+    path: String,
+}
+
+/// The minimal facts `PathResolver` needs about one MFT entry: its current
+/// sequence number (to detect recycling) and, if it has one, the parent
+/// reference and name taken from its `$FILE_NAME` attribute. Kept separate
+/// from `MftEntry` so the cache/sequence/orphan/cycle logic in
+/// `PathResolver` can be driven by a fake `EntrySource` in tests, without a
+/// live volume or any Windows API.
+struct EntryLookup {
+    sequence: u16,
+    file_name: Option<(FileReference, String)>,
+}
+
+/// A source `PathResolver` can read one MFT entry's identity from.
+/// Implemented for `WindowsLiveNtfs`; tests implement it for an in-memory map.
+trait EntrySource {
+    fn lookup(&mut self, entry_id: u64) -> Result<EntryLookup, WinThingError>;
+}
+
+impl EntrySource for WindowsLiveNtfs {
+    fn lookup(&mut self, entry_id: u64) -> Result<EntryLookup, WinThingError> {
+        let entry = self.get_mft_entry(entry_id as i64)?;
+        let file_name = entry
+            .iter_attributes()
+            .filter_map(Result::ok)
+            .find_map(|attribute| match attribute.data {
+                MftAttributeContent::AttrX30(name_attr) => {
+                    Some((name_attr.parent, name_attr.name))
+                }
+                _ => None,
+            });
+
+        Ok(EntryLookup {
+            sequence: entry.header.sequence_value,
+            file_name,
+        })
+    }
+}
+
+/// Reconstructs full paths for MFT entries by walking parent `FileReference`s
+/// stored in `$FILE_NAME` attributes back up to the volume root, caching
+/// `entry -> path` lookups so repeated ancestors are only read once.
+pub struct PathResolver<'a, S: EntrySource = WindowsLiveNtfs> {
+    live_volume: &'a mut S,
+    cache: HashMap<u64, CachedPath>,
+}
+impl<'a, S: EntrySource> PathResolver<'a, S> {
+    pub fn new(live_volume: &'a mut S) -> Self {
+        Self {
+            live_volume,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Resolve the full path of `entry_id`, walking parents up to the
+    /// volume root. A parent that cannot be read, or a reference cycle,
+    /// is rooted under a synthetic `$Orphan` node. When `expected_sequence`
+    /// is known (e.g. from a USN record's own `file_reference.sequence`),
+    /// a cache hit or freshly-read entry that doesn't match it is treated
+    /// as stale - the record was recycled for a different file - rather
+    /// than trusted.
+    pub fn resolve(&mut self, entry_id: u64, expected_sequence: Option<u16>) -> String {
+        let mut visited = HashSet::new();
+        self.resolve_inner(entry_id, expected_sequence, &mut visited)
+    }
+
+    /// Resolve one path per `$FILE_NAME` attribute on `entry`, so
+    /// hard-linked files surface every path they are known by.
+    pub fn resolve_all(&mut self, entry: &MftEntry) -> Vec<String> {
+        entry
+            .iter_attributes()
+            .filter_map(Result::ok)
+            .filter_map(|attribute| match attribute.data {
+                MftAttributeContent::AttrX30(name_attr) => Some(name_attr),
+                _ => None,
+            })
+            .map(|name_attr| {
+                let mut visited = HashSet::new();
+                self.join_with_parent(&name_attr.parent, &name_attr.name, &mut visited)
+            })
+            .collect()
+    }
+
+    fn join_with_parent(
+        &mut self,
+        parent_ref: &FileReference,
+        name: &str,
+        visited: &mut HashSet<u64>,
+    ) -> String {
+        let parent_path = self.resolve_inner(parent_ref.entry, Some(parent_ref.sequence), visited);
+        if parent_path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}\\{}", parent_path, name)
+        }
+    }
+
+    /// Resolve `entry_id`, optionally validating it against the sequence
+    /// number recorded for it in a child's `$FILE_NAME.parent`. A cache hit
+    /// whose sequence doesn't match is re-read from the volume; a freshly-read
+    /// entry whose sequence doesn't match means the MFT record has since been
+    /// recycled for a different file, so it is treated as an orphan rather
+    /// than trusted.
+    fn resolve_inner(
+        &mut self,
+        entry_id: u64,
+        expected_sequence: Option<u16>,
+        visited: &mut HashSet<u64>,
+    ) -> String {
+        if let Some(cached) = self.cache.get(&entry_id) {
+            if expected_sequence.map_or(true, |seq| seq == cached.sequence) {
+                return cached.path.clone();
+            }
+            // Stale: the record was recycled since this was cached; re-read it below.
+        }
+
+        if entry_id == ROOT_ENTRY {
+            self.cache.insert(
+                entry_id,
+                CachedPath {
+                    sequence: expected_sequence.unwrap_or(0),
+                    path: String::new(),
+                },
+            );
+            return String::new();
+        }
+
+        if !visited.insert(entry_id) {
+            return ORPHAN_NAME.to_string();
+        }
+
+        let lookup = match self.live_volume.lookup(entry_id) {
+            Ok(l) => l,
+            Err(_) => {
+                self.cache.insert(
+                    entry_id,
+                    CachedPath {
+                        sequence: expected_sequence.unwrap_or(0),
+                        path: ORPHAN_NAME.to_string(),
+                    },
+                );
+                return ORPHAN_NAME.to_string();
+            }
+        };
+
+        let actual_sequence = lookup.sequence;
+        if let Some(expected) = expected_sequence {
+            if expected != actual_sequence {
+                return ORPHAN_NAME.to_string();
+            }
+        }
+
+        let path = match lookup.file_name {
+            Some((parent, name)) => self.join_with_parent(&parent, &name, visited),
+            None => ORPHAN_NAME.to_string(),
+        };
+
+        self.cache.insert(
+            entry_id,
+            CachedPath {
+                sequence: actual_sequence,
+                path: path.clone(),
+            },
+        );
+        path
+    }
+}
+
+/// The output format that the listeners write entries out in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Jsonl,
+    Csv,
+}
+
+/// Quote a CSV cell per RFC 4180 whenever it contains a comma, quote, or
+/// line break, doubling any embedded quote. Used for every CSV row the
+/// listeners write so file names and paths containing commas don't shift
+/// the columns that follow them.
+pub fn csv_quote(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Render already-stringified cells as one RFC 4180 CSV line.
+pub fn csv_line(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|field| csv_quote(field))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// A single, flattened view of an `MftEntry` with one stable set of
+/// columns, suitable for CSV export or spreadsheet/forensic tooling
+/// that cannot consume the nested JSONL view.
+#[derive(Debug)]
+pub struct FlatEntry {
+    pub entry_id: u64,
+    pub full_name: String,
+    pub allocated_size: u64,
+    pub is_directory: bool,
+    pub is_deleted: bool,
+    pub has_alternate_data_streams: bool,
+    pub si_created: Option<NaiveDateTime>,
+    pub si_modified: Option<NaiveDateTime>,
+    pub si_mft_modified: Option<NaiveDateTime>,
+    pub si_accessed: Option<NaiveDateTime>,
+    pub fn_created: Option<NaiveDateTime>,
+    pub fn_modified: Option<NaiveDateTime>,
+    pub fn_mft_modified: Option<NaiveDateTime>,
+    pub fn_accessed: Option<NaiveDateTime>,
+}
+
+/// The stable CSV column order for `FlatEntry`, shared by every writer so
+/// the header line and each row always line up.
+pub const FLAT_ENTRY_CSV_COLUMNS: &[&str] = &[
+    "entry_id",
+    "full_name",
+    "allocated_size",
+    "is_directory",
+    "is_deleted",
+    "has_alternate_data_streams",
+    "si_created",
+    "si_modified",
+    "si_mft_modified",
+    "si_accessed",
+    "fn_created",
+    "fn_modified",
+    "fn_mft_modified",
+    "fn_accessed",
+];
+impl FlatEntry {
+    /// Flatten an `MftEntry` into a single row, preferring the Win32
+    /// namespace `$FILE_NAME` when an entry carries more than one.
+    pub fn from_entry(entry: &MftEntry) -> Result<Self, WinThingError> {
+        let is_directory = entry.is_dir();
+        let is_deleted = !entry.header.flags.contains(EntryFlags::ALLOCATED);
+
+        let mut si_attr: Option<StandardInfoAttr> = None;
+        let mut fn_attr: Option<FileNameAttr> = None;
+        let mut has_alternate_data_streams = false;
+
+        for attribute in entry.iter_attributes().filter_map(Result::ok) {
+            match &attribute.data {
+                MftAttributeContent::AttrX10(info) => {
+                    si_attr = Some(info.to_owned());
+                }
+                MftAttributeContent::AttrX30(name_attr) => {
+                    let is_preferred = name_attr.namespace == FileNamespace::Win32
+                        || fn_attr.is_none();
+                    if is_preferred {
+                        fn_attr = Some(name_attr.to_owned());
+                    }
+                }
+                _ => {}
+            }
+
+            if attribute.header.type_code == MftAttributeType::DATA {
+                if let Some(name) = &attribute.header.name {
+                    if !name.is_empty() {
+                        has_alternate_data_streams = true;
+                    }
+                }
+            }
+        }
+
+        Ok(FlatEntry {
+            entry_id: entry.header.record_number,
+            full_name: fn_attr.as_ref().map(|f| f.name.clone()).unwrap_or_default(),
+            allocated_size: fn_attr.as_ref().map(|f| f.physical_size).unwrap_or(0),
+            is_directory,
+            is_deleted,
+            has_alternate_data_streams,
+            si_created: si_attr.as_ref().map(|a| a.created),
+            si_modified: si_attr.as_ref().map(|a| a.modified),
+            si_mft_modified: si_attr.as_ref().map(|a| a.mft_modified),
+            si_accessed: si_attr.as_ref().map(|a| a.accessed),
+            fn_created: fn_attr.as_ref().map(|a| a.created),
+            fn_modified: fn_attr.as_ref().map(|a| a.modified),
+            fn_mft_modified: fn_attr.as_ref().map(|a| a.mft_modified),
+            fn_accessed: fn_attr.as_ref().map(|a| a.accessed),
+        })
+    }
+
+    /// Render this entry as one row in `FLAT_ENTRY_CSV_COLUMNS` order.
+    /// Callers still need to pass each cell through `csv_quote`/`csv_line`.
+    pub fn to_csv_row(&self) -> Vec<String> {
+        let opt_timestamp = |ts: Option<NaiveDateTime>| ts.map(|t| t.to_string()).unwrap_or_default();
+
+        vec![
+            self.entry_id.to_string(),
+            self.full_name.clone(),
+            self.allocated_size.to_string(),
+            self.is_directory.to_string(),
+            self.is_deleted.to_string(),
+            self.has_alternate_data_streams.to_string(),
+            opt_timestamp(self.si_created),
+            opt_timestamp(self.si_modified),
+            opt_timestamp(self.si_mft_modified),
+            opt_timestamp(self.si_accessed),
+            opt_timestamp(self.fn_created),
+            opt_timestamp(self.fn_modified),
+            opt_timestamp(self.fn_mft_modified),
+            opt_timestamp(self.fn_accessed),
+        ]
+    }
+}
+
 fn get_attr_name(attribute: &MftAttributeType) -> String {
     match attribute {
         MftAttributeType::StandardInformation => "StandardInformation".to_string(),
@@ -29,14 +362,76 @@ fn get_attr_name(attribute: &MftAttributeType) -> String {
     }
 }
 
-/// Generate a custom JSON view of the mft entry
-pub fn custom_entry_value(entry: MftEntry) -> Result<Value, WinThingError> {
+/// Logical size of a `$DATA` attribute's content, read directly off its
+/// resident or non-resident header.
+fn attribute_logical_size(attribute: &MftAttribute) -> Option<u64> {
+    match &attribute.header.residential_header {
+        ResidentialHeader::Resident(resident) => Some(resident.data_size as u64),
+        ResidentialHeader::NonResident(non_resident) => Some(non_resident.file_size),
+    }
+}
+
+/// Derive the security-relevant facts analysts query for most often - named
+/// alternate data streams, deletion/directory state, and the presence of a
+/// reparse point or object id - without having to re-walk the nested
+/// `attributes` map in the full JSON view.
+fn build_summary(entry: &MftEntry, attributes: &[MftAttribute]) -> Value {
+    let is_directory = entry.is_dir();
+    let is_deleted = !entry.header.flags.contains(EntryFlags::ALLOCATED);
+
+    let mut alternate_data_streams = Vec::new();
+    let mut has_reparse_point = false;
+    let mut has_object_id = false;
+
+    for attribute in attributes {
+        match attribute.header.type_code {
+            MftAttributeType::DATA => {
+                if let Some(name) = &attribute.header.name {
+                    if !name.is_empty() {
+                        alternate_data_streams.push(json!({
+                            "name": name,
+                            "logical_size": attribute_logical_size(attribute),
+                        }));
+                    }
+                }
+            }
+            MftAttributeType::ReparsePoint => has_reparse_point = true,
+            MftAttributeType::ObjectId => has_object_id = true,
+            _ => {}
+        }
+    }
+
+    json!({
+        "is_directory": is_directory,
+        "is_deleted": is_deleted,
+        "has_reparse_point": has_reparse_point,
+        "has_object_id": has_object_id,
+        "alternate_data_streams": alternate_data_streams,
+    })
+}
+
+/// Generate a custom JSON view of the mft entry. When `resolver` is given,
+/// a `full_path` array is added with one resolved path per `$FILE_NAME`
+/// attribute on the entry (see `PathResolver`). A `summary` object is
+/// always added with the ADS/deletion/reparse-point/object-id facts
+/// analysts otherwise have to re-derive from the nested attribute map.
+pub fn custom_entry_value(
+    entry: &MftEntry,
+    resolver: Option<&mut PathResolver>,
+) -> Result<Value, WinThingError> {
     let mut entry_value = json!({});
 
     entry_value["header"] = to_value(&entry.header)?;
     entry_value["attributes"] = json!({});
 
+    if let Some(resolver) = resolver {
+        entry_value["full_path"] = json!(resolver.resolve_all(entry));
+    }
+
     let attributes: Vec<MftAttribute> = entry.iter_attributes().filter_map(Result::ok).collect();
+
+    entry_value["summary"] = build_summary(entry, &attributes);
+
     for attribute in attributes {
         let attr_type_str = get_attr_name(&attribute.header.type_code);
         let instance = attribute.header.instance.to_string();
@@ -49,17 +444,10 @@ pub fn custom_entry_value(entry: MftEntry) -> Result<Value, WinThingError> {
     Ok(entry_value)
 }
 
-fn listen_mft(mut listener: EntryListener, tx: Sender<Value>) -> Result<(), WinThingError> {
+fn listen_mft(mut listener: EntryListener, tx: Sender<String>) -> Result<(), WinThingError> {
+    let output_format = listener.output_format;
     let mut previous_value = listener.get_current_value()?;
 
-    // Send the raw original value
-    // match tx.send(previous_value.clone()) {
-    //     Ok(_) => {},
-    //     Err(error) => {
-    //         eprintln!("error sending value: {:?}", error);
-    //     }
-    // }
-
     let volume_str = listener.get_volume_string()?;
     let usn_config = UsnListenerConfig::new().enumerate_paths(false);
     let usn_listener = usn_config.get_listener(&volume_str);
@@ -77,18 +465,30 @@ fn listen_mft(mut listener: EntryListener, tx: Sender<Value>) -> Result<(), WinT
             continue;
         }
 
-        let current_value = listener
-            .get_current_value()
+        let (current_entry, current_value) = listener
+            .get_current_entry_and_value()
             .expect("Unable to get current mft entry value");
 
         let difference_value = get_difference_value(&previous_value, &current_value);
 
         if difference_value.is_object() {
             if !difference_value.as_object().unwrap().is_empty() {
-                match tx.send(difference_value) {
-                    Ok(_) => {}
+                let line: Result<String, WinThingError> = match output_format {
+                    OutputFormat::Jsonl => {
+                        serde_json::to_string(&difference_value).map_err(WinThingError::from)
+                    }
+                    OutputFormat::Csv => FlatEntry::from_entry(&current_entry)
+                        .map(|flat| csv_line(&flat.to_csv_row())),
+                };
+
+                match line {
+                    Ok(line) => {
+                        if let Err(error) = tx.send(line) {
+                            eprintln!("error sending value: {:?}", error);
+                        }
+                    }
                     Err(error) => {
-                        eprintln!("error sending value: {:?}", error);
+                        eprintln!("error formatting value: {:?}", error);
                     }
                 }
             }
@@ -135,6 +535,7 @@ pub struct EntryListener {
     live_volume: WindowsLiveNtfs,
     pub path_to_monitor: String,
     pub entry_to_monitor: i64,
+    output_format: OutputFormat,
 }
 impl EntryListener {
     pub fn new(path_to_monitor: &str) -> Result<Self, WinThingError> {
@@ -148,21 +549,44 @@ impl EntryListener {
             live_volume: live_volume,
             path_to_monitor: path_to_monitor.to_string(),
             entry_to_monitor: entry as i64,
+            output_format: OutputFormat::Jsonl,
         })
     }
 
+    /// Set the format `listen_to_file` writes records out in. Defaults to
+    /// `OutputFormat::Jsonl`.
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
     pub fn get_volume_string(&self) -> Result<String, WinThingError> {
         get_volume_path_from_path(&self.path_to_monitor)
     }
 
     pub fn get_current_value(&mut self) -> Result<Value, WinThingError> {
+        let (_entry, value) = self.get_current_entry_and_value()?;
+        Ok(value)
+    }
+
+    /// Fetch both the raw current `MftEntry` and its custom JSON view from a
+    /// single read of that entry (resolving `full_path` still reads its
+    /// parents), so callers that need the raw entry (e.g. to flatten it for
+    /// CSV) don't have to read it again.
+    fn get_current_entry_and_value(&mut self) -> Result<(MftEntry, Value), WinThingError> {
         let mft_entry = self.live_volume.get_mft_entry(self.entry_to_monitor)?;
+        let value = {
+            let mut resolver = PathResolver::new(&mut self.live_volume);
+            custom_entry_value(&mft_entry, Some(&mut resolver))?
+        };
 
-        custom_entry_value(mft_entry)
+        Ok((mft_entry, value))
     }
 
-    pub fn listen_to_file(self) -> Result<Receiver<Value>, WinThingError> {
-        let (tx, rx): (Sender<Value>, Receiver<Value>) = channel::unbounded();
+    /// Start listening in the background, returning one formatted line
+    /// (JSONL or CSV, per `output_format`) per detected change.
+    pub fn listen_to_file(self) -> Result<Receiver<String>, WinThingError> {
+        let (tx, rx): (Sender<String>, Receiver<String>) = channel::unbounded();
 
         let _thread = thread::spawn(move || match listen_mft(self, tx) {
             Ok(_) => println!("thread terminated"),
@@ -172,3 +596,143 @@ impl EntryListener {
         Ok(rx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_quote_leaves_plain_fields_alone() {
+        assert_eq!(csv_quote("C:\\Windows\\System32"), "C:\\Windows\\System32");
+        assert_eq!(csv_quote(""), "");
+    }
+
+    #[test]
+    fn csv_quote_escapes_commas_quotes_and_newlines() {
+        assert_eq!(csv_quote("a,b"), "\"a,b\"");
+        assert_eq!(csv_quote("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_quote("line1\nline2"), "\"line1\nline2\"");
+        assert_eq!(csv_quote("line1\rline2"), "\"line1\rline2\"");
+    }
+
+    #[test]
+    fn csv_line_joins_quoted_fields() {
+        let fields = vec!["a,b".to_string(), "plain".to_string(), "".to_string()];
+        assert_eq!(csv_line(&fields), "\"a,b\",plain,");
+    }
+
+    /// An in-memory `EntrySource` so `PathResolver`'s cache/sequence/orphan/
+    /// cycle handling can be exercised without a live volume or any Windows API.
+    /// Stores plain fields rather than `EntryLookup` itself since `FileReference`
+    /// (from the `mft` crate) isn't `Clone`.
+    struct FakeVolume {
+        entries: HashMap<u64, (u16, Option<(u64, u16, String)>)>,
+    }
+
+    impl FakeVolume {
+        fn new() -> Self {
+            Self {
+                entries: HashMap::new(),
+            }
+        }
+
+        fn add(&mut self, entry_id: u64, sequence: u16, parent: Option<(u64, u16, &str)>) {
+            let parent = parent.map(|(parent_entry, parent_sequence, name)| {
+                (parent_entry, parent_sequence, name.to_string())
+            });
+            self.entries.insert(entry_id, (sequence, parent));
+        }
+    }
+
+    impl EntrySource for FakeVolume {
+        fn lookup(&mut self, entry_id: u64) -> Result<EntryLookup, WinThingError> {
+            self.entries
+                .get(&entry_id)
+                .map(|(sequence, parent)| EntryLookup {
+                    sequence: *sequence,
+                    file_name: parent.as_ref().map(|(entry, seq, name)| {
+                        (
+                            FileReference {
+                                entry: *entry,
+                                sequence: *seq,
+                            },
+                            name.clone(),
+                        )
+                    }),
+                })
+                .ok_or_else(|| {
+                    WinThingError::from(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "no such entry",
+                    ))
+                })
+        }
+    }
+
+    #[test]
+    fn resolve_walks_parents_up_to_root() {
+        let mut volume = FakeVolume::new();
+        volume.add(ROOT_ENTRY, 1, None);
+        volume.add(10, 1, Some((ROOT_ENTRY, 1, "dir")));
+        volume.add(20, 1, Some((10, 1, "file.txt")));
+
+        let mut resolver = PathResolver::new(&mut volume);
+        assert_eq!(resolver.resolve(20, None), "dir\\file.txt");
+    }
+
+    #[test]
+    fn resolve_uses_cache_on_second_lookup() {
+        let mut volume = FakeVolume::new();
+        volume.add(ROOT_ENTRY, 1, None);
+        volume.add(10, 1, Some((ROOT_ENTRY, 1, "dir")));
+
+        let mut resolver = PathResolver::new(&mut volume);
+        assert_eq!(resolver.resolve(10, None), "dir");
+        // Remove the backing entry entirely; a cache hit must not re-read it.
+        resolver.live_volume.entries.remove(&10);
+        assert_eq!(resolver.resolve(10, None), "dir");
+    }
+
+    #[test]
+    fn resolve_orphans_a_recycled_entry_even_without_a_parent_link() {
+        let mut volume = FakeVolume::new();
+        volume.add(ROOT_ENTRY, 1, None);
+        volume.add(10, 1, Some((ROOT_ENTRY, 1, "dir")));
+
+        let mut resolver = PathResolver::new(&mut volume);
+        assert_eq!(resolver.resolve(10, Some(1)), "dir");
+
+        // The record gets recycled for a different file at a new sequence.
+        resolver.live_volume.add(10, 2, Some((ROOT_ENTRY, 1, "other.txt")));
+
+        // A caller with the current sequence misses the stale cache entry
+        // and sees the fresh entry instead.
+        assert_eq!(resolver.resolve(10, Some(2)), "other.txt");
+        // A caller that still holds the old sequence gets neither the old
+        // path nor the new entry's path.
+        assert_eq!(resolver.resolve(10, Some(1)), ORPHAN_NAME);
+    }
+
+    #[test]
+    fn resolve_orphans_entries_that_cannot_be_read() {
+        let mut volume = FakeVolume::new();
+        volume.add(ROOT_ENTRY, 1, None);
+
+        let mut resolver = PathResolver::new(&mut volume);
+        assert_eq!(resolver.resolve(999, None), ORPHAN_NAME);
+    }
+
+    #[test]
+    fn resolve_breaks_reference_cycles() {
+        let mut volume = FakeVolume::new();
+        volume.add(ROOT_ENTRY, 1, None);
+        // 10 and 20 each claim the other as their parent.
+        volume.add(10, 1, Some((20, 1, "a")));
+        volume.add(20, 1, Some((10, 1, "b")));
+
+        let mut resolver = PathResolver::new(&mut volume);
+        // The walk 10 -> 20 -> 10 stops at the repeated entry, which
+        // resolves to the orphan node.
+        assert_eq!(resolver.resolve(10, None), format!("{}\\b\\a", ORPHAN_NAME));
+    }
+}